@@ -1,8 +1,17 @@
-use clap::Parser;
-use rayon::prelude::*;
-use reqwest::blocking::Client;
+use clap::{Parser, ValueEnum};
+use futures::stream::{self, StreamExt};
+use hmac::{Hmac, Mac};
+use reqwest::{Client, RequestBuilder, Response};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::error::Error as StdError;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::io::{self, BufRead, BufReader, IsTerminal, Write as _};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+type HmacSha256 = Hmac<Sha256>;
 
 #[derive(Debug)]
 enum Provider {
@@ -20,11 +29,226 @@ struct Args {
     /// Input file containing bucket names (one per line). If not specified, bucket names are read from stdin.
     #[arg(short = 'i', long)]
     input: Option<String>,
+
+    /// AWS access key ID used to sign requests (falls back to AWS_ACCESS_KEY_ID)
+    #[arg(long)]
+    access_key: Option<String>,
+
+    /// AWS secret access key used to sign requests (falls back to AWS_SECRET_ACCESS_KEY)
+    #[arg(long)]
+    secret_key: Option<String>,
+
+    /// AWS session token for temporary credentials (falls back to AWS_SESSION_TOKEN)
+    #[arg(long)]
+    session_token: Option<String>,
+
+    /// AWS region to sign requests for (falls back to AWS_DEFAULT_REGION, default us-east-1)
+    #[arg(long)]
+    region: Option<String>,
+
+    /// List and print the object keys found in readable buckets, instead of just flagging them
+    #[arg(long)]
+    list: bool,
+
+    /// Maximum number of object keys to enumerate per bucket when --list is set
+    #[arg(long, default_value_t = 1000)]
+    max_keys: usize,
+
+    /// Leave the write-test object in place instead of deleting it after a successful PUT
+    #[arg(long)]
+    no_cleanup: bool,
+
+    /// Emit machine-readable results instead of colored terminal output
+    #[arg(short = 'o', long, value_enum)]
+    output: Option<OutputFormat>,
+
+    /// Probe bucket/object ACL and policy endpoints for public grants (AllUsers, AuthenticatedUsers)
+    #[arg(long)]
+    acl: bool,
+
+    /// Maximum number of buckets to probe concurrently
+    #[arg(long, default_value_t = 20)]
+    concurrency: usize,
+
+    /// Cap outbound requests to this many per second (unlimited if unset)
+    #[arg(long, value_parser = parse_positive_rate_limit)]
+    rate_limit: Option<f64>,
+
+    /// Per-request timeout, in seconds
+    #[arg(long, default_value_t = 10)]
+    timeout_secs: u64,
+
+    /// Retries for transient errors (connection reset, 429, 5xx), with exponential backoff
+    #[arg(long, default_value_t = 3)]
+    max_retries: u32,
+}
+
+/// Validate `--rate-limit`: it becomes a `Duration` divisor, so zero, negative and
+/// non-finite values must be rejected here rather than panicking once the scan starts.
+fn parse_positive_rate_limit(raw: &str) -> Result<f64, String> {
+    let value: f64 = raw.parse().map_err(|_| format!("`{}` is not a number", raw))?;
+    if value.is_finite() && value > 0.0 {
+        Ok(value)
+    } else {
+        Err("--rate-limit must be a positive number".to_string())
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum OutputFormat {
+    Json,
+    Jsonl,
+    Csv,
 }
 
 struct BucketTarget {
     provider: Provider,
     bucket: String,
+    /// Region embedded in the input, resolved via discovery, or overridden with
+    /// `--region`. `None` means "use the provider's default region".
+    region: Option<String>,
+}
+
+/// Static AWS credentials used to sign probe requests with SigV4, so buckets that
+/// deny anonymous access but allow any authenticated AWS principal are still detected.
+struct Credentials {
+    access_key: String,
+    secret_key: String,
+    session_token: Option<String>,
+    region: String,
+}
+
+impl Credentials {
+    fn from_env_or_args(args: &Args) -> Option<Self> {
+        let access_key = args
+            .access_key
+            .clone()
+            .or_else(|| std::env::var("AWS_ACCESS_KEY_ID").ok())?;
+        let secret_key = args
+            .secret_key
+            .clone()
+            .or_else(|| std::env::var("AWS_SECRET_ACCESS_KEY").ok())?;
+        let session_token = args
+            .session_token
+            .clone()
+            .or_else(|| std::env::var("AWS_SESSION_TOKEN").ok());
+        let region = args
+            .region
+            .clone()
+            .or_else(|| std::env::var("AWS_DEFAULT_REGION").ok())
+            .unwrap_or_else(|| "us-east-1".to_string());
+        Some(Credentials { access_key, secret_key, session_token, region })
+    }
+}
+
+/// Read access for a bucket: whether anyone can read it, only authenticated
+/// AWS principals can, or nobody (that we could probe) can.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum AccessLevel {
+    Public,
+    AuthenticatedOnly,
+    Private,
+}
+
+/// A token-bucket limiter shared across all in-flight probes, so the whole scan
+/// respects a single requests/sec budget regardless of how many tasks are running.
+struct RateLimiter {
+    interval: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    fn new(requests_per_sec: f64) -> Self {
+        // A vanishingly small rate (e.g. 1e-20) would make 1.0 / requests_per_sec overflow
+        // what Duration can represent and panic; clamp to an effectively-never-fires cap
+        // instead, since no real scan needs a gap longer than this anyway.
+        const MAX_INTERVAL_SECS: f64 = 86_400.0;
+        let interval_secs = (1.0 / requests_per_sec).min(MAX_INTERVAL_SECS);
+        RateLimiter {
+            interval: Duration::from_secs_f64(interval_secs),
+            next_slot: Mutex::new(Instant::now()),
+        }
+    }
+
+    async fn acquire(&self) {
+        let mut next_slot = self.next_slot.lock().await;
+        let now = Instant::now();
+        let scheduled = (*next_slot).max(now);
+        *next_slot = scheduled + self.interval;
+        drop(next_slot);
+        let wait = scheduled.saturating_duration_since(now);
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Timeout, retry and rate-limit policy applied to every outbound probe request.
+struct RequestPolicy {
+    rate_limiter: Option<RateLimiter>,
+    timeout: Duration,
+    max_retries: u32,
+}
+
+impl RequestPolicy {
+    fn new(args: &Args) -> Self {
+        RequestPolicy {
+            rate_limiter: args.rate_limit.map(RateLimiter::new),
+            timeout: Duration::from_secs(args.timeout_secs),
+            max_retries: args.max_retries,
+        }
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        Duration::from_millis(200 * 2u64.saturating_pow(attempt))
+    }
+}
+
+fn is_transient_error(err: &reqwest::Error) -> bool {
+    if err.is_timeout() || err.is_connect() {
+        return true;
+    }
+    err.source()
+        .and_then(|source| source.downcast_ref::<std::io::Error>())
+        .map(|io_err| io_err.kind() == std::io::ErrorKind::ConnectionReset)
+        .unwrap_or(false)
+}
+
+/// Send `req`, retrying with exponential backoff on connection resets, timeouts, 429s
+/// and 5xx responses, and pacing every attempt through the shared rate limiter.
+async fn send_with_policy(req: RequestBuilder, policy: &RequestPolicy) -> Option<Response> {
+    for attempt in 0..=policy.max_retries {
+        if let Some(rate_limiter) = &policy.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+        let attempt_req = req.try_clone()?;
+        match tokio::time::timeout(policy.timeout, attempt_req.send()).await {
+            Ok(Ok(resp)) => {
+                let status = resp.status();
+                let transient_status = status.as_u16() == 429 || status.is_server_error();
+                if transient_status && attempt < policy.max_retries {
+                    tokio::time::sleep(policy.backoff(attempt)).await;
+                    continue;
+                }
+                return Some(resp);
+            }
+            Ok(Err(err)) => {
+                if is_transient_error(&err) && attempt < policy.max_retries {
+                    tokio::time::sleep(policy.backoff(attempt)).await;
+                    continue;
+                }
+                return None;
+            }
+            Err(_elapsed) => {
+                if attempt < policy.max_retries {
+                    tokio::time::sleep(policy.backoff(attempt)).await;
+                    continue;
+                }
+                return None;
+            }
+        }
+    }
+    None
 }
 
 fn print_banner() {
@@ -41,77 +265,140 @@ fn extract_target(line: &str) -> Option<BucketTarget> {
         return None;
     }
 
-    // AWS S3
+    // AWS S3 (global endpoint)
     if trimmed.ends_with(".s3.amazonaws.com") {
         let bucket = trimmed.trim_end_matches(".s3.amazonaws.com").to_string();
-        return Some(BucketTarget { provider: Provider::AwsS3, bucket });
+        return Some(BucketTarget { provider: Provider::AwsS3, bucket, region: None });
     }
     if trimmed.starts_with("s3.amazonaws.com/") {
         let parts: Vec<&str> = trimmed.split('/').collect();
         if parts.len() >= 2 {
-            return Some(BucketTarget { provider: Provider::AwsS3, bucket: parts[1].to_string() });
+            return Some(BucketTarget { provider: Provider::AwsS3, bucket: parts[1].to_string(), region: None });
+        }
+    }
+    // AWS S3 (regional virtual-hosted style, e.g. bucket.s3.us-west-2.amazonaws.com
+    // or the legacy bucket.s3-us-west-2.amazonaws.com)
+    if trimmed.ends_with(".amazonaws.com") {
+        let without_suffix = trimmed.trim_end_matches(".amazonaws.com");
+        if let Some(idx) = without_suffix.find(".s3") {
+            let bucket = without_suffix[..idx].to_string();
+            let region = without_suffix[idx + 3..].trim_start_matches(['.', '-']);
+            if !bucket.is_empty() && !region.is_empty() {
+                return Some(BucketTarget { provider: Provider::AwsS3, bucket, region: Some(region.to_string()) });
+            }
         }
     }
 
-    // DigitalOcean Spaces (region.digitaloceanspaces.com)
+    // DigitalOcean Spaces (bucket.region.digitaloceanspaces.com)
     if trimmed.ends_with(".digitaloceanspaces.com") {
-        let bucket = trimmed.split('.').next().unwrap_or(trimmed).to_string();
-        return Some(BucketTarget { provider: Provider::DigitalOceanSpaces, bucket });
+        let parts: Vec<&str> = trimmed.split('.').collect();
+        let bucket = parts.first().copied().unwrap_or(trimmed).to_string();
+        let region = (parts.len() > 3).then(|| parts[1].to_string());
+        return Some(BucketTarget { provider: Provider::DigitalOceanSpaces, bucket, region });
     }
 
-    // Linode Object Storage (region.linodeobjects.com)
+    // Linode Object Storage (bucket.region.linodeobjects.com)
     if trimmed.ends_with(".linodeobjects.com") {
-        let bucket = trimmed.split('.').next().unwrap_or(trimmed).to_string();
-        return Some(BucketTarget { provider: Provider::LinodeObjStorage, bucket });
+        let parts: Vec<&str> = trimmed.split('.').collect();
+        let bucket = parts.first().copied().unwrap_or(trimmed).to_string();
+        let region = (parts.len() > 3).then(|| parts[1].to_string());
+        return Some(BucketTarget { provider: Provider::LinodeObjStorage, bucket, region });
     }
 
     // Azure Blob ([container].blob.core.windows.net)
     if trimmed.ends_with(".blob.core.windows.net") {
         let container = trimmed.split('.').next().unwrap_or(trimmed).to_string();
-        return Some(BucketTarget { provider: Provider::AzureBlob, bucket: container });
+        return Some(BucketTarget { provider: Provider::AzureBlob, bucket: container, region: None });
     }
 
     // GCP ([bucket].storage.googleapis.com or storage.googleapis.com/[bucket])
     if trimmed.ends_with(".storage.googleapis.com") {
         let bucket = trimmed.trim_end_matches(".storage.googleapis.com").to_string();
-        return Some(BucketTarget { provider: Provider::GcpStorage, bucket });
+        return Some(BucketTarget { provider: Provider::GcpStorage, bucket, region: None });
     }
     if trimmed.starts_with("storage.googleapis.com/") {
         let parts: Vec<&str> = trimmed.split('/').collect();
         if parts.len() >= 2 {
-            return Some(BucketTarget { provider: Provider::GcpStorage, bucket: parts[1].to_string() });
+            return Some(BucketTarget { provider: Provider::GcpStorage, bucket: parts[1].to_string(), region: None });
         }
     }
 
     // Fallback: try as AWS, else unknown
-    Some(BucketTarget { provider: Provider::Unknown, bucket: trimmed.to_string() })
+    Some(BucketTarget { provider: Provider::Unknown, bucket: trimmed.to_string(), region: None })
 }
 
 fn construct_read_url(t: &BucketTarget) -> String {
     match t.provider {
-        Provider::AwsS3 | Provider::DigitalOceanSpaces | Provider::LinodeObjStorage => format!("http://{}.{}", t.bucket, get_provider_domain(&t.provider)),
+        Provider::AwsS3 | Provider::DigitalOceanSpaces | Provider::LinodeObjStorage => format!("http://{}.{}", t.bucket, get_provider_domain(&t.provider, t.region.as_deref())),
         Provider::AzureBlob => format!("https://{}.blob.core.windows.net/?restype=container&comp=list", t.bucket),
         Provider::GcpStorage => format!("https://storage.googleapis.com/{}/", t.bucket),
         Provider::Unknown => format!("http://{}", t.bucket),
     }
 }
 
+const WRITE_TEST_OBJECT: &str = "codecompanion-test-object.txt";
+
 fn construct_write_url(t: &BucketTarget) -> String {
-    let test_object = "codecompanion-test-object.txt";
     match t.provider {
-        Provider::AwsS3 | Provider::DigitalOceanSpaces | Provider::LinodeObjStorage => format!("http://{}.{}{}", t.bucket, get_provider_domain(&t.provider), format!("/{}", test_object)),
-        Provider::AzureBlob => format!("https://{}.blob.core.windows.net/{}", t.bucket, test_object),
-        Provider::GcpStorage => format!("https://storage.googleapis.com/{}/{}", t.bucket, test_object),
-        Provider::Unknown => format!("http://{}/{}", t.bucket, test_object),
+        Provider::AwsS3 | Provider::DigitalOceanSpaces | Provider::LinodeObjStorage => format!("http://{}.{}/{}", t.bucket, get_provider_domain(&t.provider, t.region.as_deref()), WRITE_TEST_OBJECT),
+        Provider::AzureBlob => format!("https://{}.blob.core.windows.net/{}", t.bucket, WRITE_TEST_OBJECT),
+        Provider::GcpStorage => format!("https://storage.googleapis.com/{}/{}", t.bucket, WRITE_TEST_OBJECT),
+        Provider::Unknown => format!("http://{}/{}", t.bucket, WRITE_TEST_OBJECT),
     }
 }
 
-fn get_provider_domain(provider: &Provider) -> &'static str {
+/// Resolve the domain to probe for S3-compatible providers, honoring a region
+/// discovered via `resolve_region` (or embedded in the input hostname) and
+/// otherwise falling back to each provider's default region.
+fn get_provider_domain(provider: &Provider, region: Option<&str>) -> String {
     match provider {
-        Provider::AwsS3 => "s3.amazonaws.com",
-        Provider::DigitalOceanSpaces => "nyc3.digitaloceanspaces.com", // default region; modify as needed
-        Provider::LinodeObjStorage => "us-east-1.linodeobjects.com",
-        _ => "",
+        Provider::AwsS3 => match region {
+            None | Some("us-east-1") => "s3.amazonaws.com".to_string(),
+            Some(region) => format!("s3.{}.amazonaws.com", region),
+        },
+        Provider::DigitalOceanSpaces => format!("{}.digitaloceanspaces.com", region.unwrap_or("nyc3")),
+        Provider::LinodeObjStorage => format!("{}.linodeobjects.com", region.unwrap_or("us-east-1")),
+        _ => String::new(),
+    }
+}
+
+/// Discover the bucket's real region so reads/writes land on the correct regional
+/// endpoint instead of being misclassified as `[no access]`. Only applies to AWS S3;
+/// other providers keep whatever region (if any) was embedded in the input hostname,
+/// so a `--region` override never gets stamped onto a DigitalOcean/Linode/other
+/// target and rewritten to an unrelated domain. For AWS, an explicit `--region`
+/// override or a region already embedded in the input hostname wins outright;
+/// otherwise we HEAD the global endpoint and read `x-amz-bucket-region` (or the
+/// `<Region>` in a redirect body).
+async fn resolve_region(client: &Client, policy: &RequestPolicy, t: &mut BucketTarget, override_region: Option<&str>) {
+    if !matches!(t.provider, Provider::AwsS3) {
+        return;
+    }
+    if let Some(region) = override_region {
+        t.region = Some(region.to_string());
+        return;
+    }
+    if t.region.is_some() {
+        return;
+    }
+    let url = format!("http://{}.{}", t.bucket, get_provider_domain(&t.provider, None));
+    let Some(resp) = send_with_policy(client.head(&url), policy).await else {
+        return;
+    };
+    if let Some(region) = resp.headers().get("x-amz-bucket-region").and_then(|v| v.to_str().ok()) {
+        t.region = Some(region.to_string());
+        return;
+    }
+    if resp.status().as_u16() == 301 {
+        // A HEAD response carries no body, so fetch the redirect body with a GET.
+        let Some(get_resp) = send_with_policy(client.get(&url), policy).await else {
+            return;
+        };
+        if let Ok(body) = get_resp.text().await {
+            if let Some(region) = extract_tag(&body, "Region") {
+                t.region = Some(region.to_string());
+            }
+        }
     }
 }
 
@@ -126,44 +413,629 @@ fn provider_str(provider: &Provider) -> &'static str {
     }
 }
 
-fn check_read(client: &Client, t: &BucketTarget) -> bool {
+fn sha256_hex(data: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data);
+    hex::encode(hasher.finalize())
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts a key of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+struct SignedHeaders {
+    authorization: String,
+    amz_date: String,
+    content_sha256: String,
+}
+
+/// Build the `Authorization` header and companion headers for an AWS SigV4-signed
+/// S3 request, per the canonical-request / string-to-sign / signing-key recipe.
+fn sign_s3_request(
+    method: &str,
+    host: &str,
+    canonical_uri: &str,
+    canonical_query: &str,
+    payload_hash: &str,
+    region: &str,
+    creds: &Credentials,
+) -> SignedHeaders {
+    let now = chrono::Utc::now();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date_stamp = now.format("%Y%m%d").to_string();
+
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+    let canonical_headers = format!(
+        "host:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n",
+        host, payload_hash, amz_date
+    );
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        method, canonical_uri, canonical_query, canonical_headers, signed_headers, payload_hash
+    );
+
+    let scope = format!("{}/{}/s3/aws4_request", date_stamp, region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        sha256_hex(canonical_request.as_bytes())
+    );
+
+    let k_date = hmac_sha256(format!("AWS4{}", creds.secret_key).as_bytes(), date_stamp.as_bytes());
+    let k_region = hmac_sha256(&k_date, region.as_bytes());
+    let k_service = hmac_sha256(&k_region, b"s3");
+    let k_signing = hmac_sha256(&k_service, b"aws4_request");
+    let signature = hex::encode(hmac_sha256(&k_signing, string_to_sign.as_bytes()));
+
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        creds.access_key, scope, signed_headers, signature
+    );
+
+    SignedHeaders { authorization, amz_date, content_sha256: payload_hash.to_string() }
+}
+
+/// Attach SigV4 auth headers to an S3 request builder for `t`, signing `canonical_uri`
+/// with `payload_hash` (use the literal `UNSIGNED-PAYLOAD` for GETs).
+fn apply_sigv4(
+    req: RequestBuilder,
+    method: &str,
+    t: &BucketTarget,
+    canonical_uri: &str,
+    payload_hash: &str,
+    creds: &Credentials,
+) -> RequestBuilder {
+    let host = format!("{}.{}", t.bucket, get_provider_domain(&t.provider, t.region.as_deref()));
+    let region = t.region.as_deref().unwrap_or(&creds.region);
+    let signed = sign_s3_request(method, &host, canonical_uri, "", payload_hash, region, creds);
+    let mut req = req
+        .header("x-amz-date", signed.amz_date)
+        .header("x-amz-content-sha256", signed.content_sha256)
+        .header("Authorization", signed.authorization);
+    if let Some(token) = &creds.session_token {
+        req = req.header("x-amz-security-token", token.clone());
+    }
+    req
+}
+
+fn matches_listing_marker(provider: &Provider, body: &str) -> bool {
+    match provider {
+        Provider::AwsS3 | Provider::DigitalOceanSpaces | Provider::LinodeObjStorage => body.contains("<ListBucketResult"),
+        Provider::AzureBlob => body.contains("EnumerationResults"),
+        Provider::GcpStorage => body.contains("ListBucketResult") || body.contains("xml"),
+        _ => false,
+    }
+}
+
+async fn probe_read(client: &Client, policy: &RequestPolicy, t: &BucketTarget, url: &str, creds: Option<&Credentials>) -> bool {
+    let mut req = client.get(url);
+    if let Some(creds) = creds {
+        req = apply_sigv4(req, "GET", t, "/", "UNSIGNED-PAYLOAD", creds);
+    }
+    match send_with_policy(req, policy).await {
+        Some(resp) if resp.status().is_success() => {
+            resp.text().await.is_ok_and(|body| matches_listing_marker(&t.provider, &body))
+        }
+        _ => false,
+    }
+}
+
+/// Probe `t` for read access anonymously, then (for AWS S3, if credentials are
+/// available) again as an authenticated AWS principal, to tell public buckets
+/// apart from ones that merely allow "any authenticated AWS user".
+async fn check_read(client: &Client, policy: &RequestPolicy, t: &BucketTarget, creds: Option<&Credentials>) -> AccessLevel {
     let url = construct_read_url(t);
-    let resp = client.get(&url).send();
-    match resp {
-        Ok(r) => match t.provider {
-            Provider::AwsS3 | Provider::DigitalOceanSpaces | Provider::LinodeObjStorage =>
-                r.status().is_success() && r.text().map_or(false, |body| body.contains("<ListBucketResult")),
-            Provider::AzureBlob =>
-                r.status().is_success() && r.text().map_or(false, |body| body.contains("EnumerationResults")),
-            Provider::GcpStorage =>
-                r.status().is_success() && r.text().map_or(false, |body| body.contains("ListBucketResult") || body.contains("xml")),
-            _ => false,
-        },
-        Err(_) => false,
+    if probe_read(client, policy, t, &url, None).await {
+        return AccessLevel::Public;
+    }
+    if matches!(t.provider, Provider::AwsS3) {
+        if let Some(creds) = creds {
+            if probe_read(client, policy, t, &url, Some(creds)).await {
+                return AccessLevel::AuthenticatedOnly;
+            }
+        }
     }
+    AccessLevel::Private
 }
 
-fn check_write(client: &Client, t: &BucketTarget) -> bool {
+async fn probe_write(client: &Client, policy: &RequestPolicy, t: &BucketTarget, url: &str, body: &'static [u8], creds: Option<&Credentials>) -> bool {
+    let mut req = client.put(url).body(body);
+    if let Some(creds) = creds {
+        let uri = format!("/{}", WRITE_TEST_OBJECT);
+        req = apply_sigv4(req, "PUT", t, &uri, &sha256_hex(body), creds);
+    }
+    match send_with_policy(req, policy).await {
+        Some(resp) => resp.status().is_success(),
+        None => false,
+    }
+}
+
+async fn probe_delete(client: &Client, policy: &RequestPolicy, t: &BucketTarget, url: &str, creds: Option<&Credentials>) -> bool {
+    let mut req = client.delete(url);
+    if let Some(creds) = creds {
+        let uri = format!("/{}", WRITE_TEST_OBJECT);
+        req = apply_sigv4(req, "DELETE", t, &uri, "UNSIGNED-PAYLOAD", creds);
+    }
+    match send_with_policy(req, policy).await {
+        Some(resp) => resp.status().is_success(),
+        None => false,
+    }
+}
+
+const WRITE_TEST_CONTENT: &[u8] = b"CodeCompanion write test";
+
+/// Probe write access, then (unless `cleanup` is false) DELETE the test object we just
+/// uploaded so a scan doesn't leave litter behind in every writable bucket.
+async fn check_write(client: &Client, policy: &RequestPolicy, t: &BucketTarget, creds: Option<&Credentials>, cleanup: bool) -> (AccessLevel, Option<bool>) {
     let url = construct_write_url(t);
-    let test_content = b"CodeCompanion write test";
-    let resp = client.put(&url).body(test_content.as_ref()).send();
-    match resp {
-        Ok(r) => r.status().is_success(),
-        Err(_) => false,
+    if probe_write(client, policy, t, &url, WRITE_TEST_CONTENT, None).await {
+        let deleted = if cleanup { Some(probe_delete(client, policy, t, &url, None).await) } else { None };
+        return (AccessLevel::Public, deleted);
+    }
+    if matches!(t.provider, Provider::AwsS3) {
+        if let Some(creds) = creds {
+            if probe_write(client, policy, t, &url, WRITE_TEST_CONTENT, Some(creds)).await {
+                let deleted = if cleanup { Some(probe_delete(client, policy, t, &url, Some(creds)).await) } else { None };
+                return (AccessLevel::AuthenticatedOnly, deleted);
+            }
+        }
+    }
+    (AccessLevel::Private, None)
+}
+
+/// A single object/blob found while enumerating a readable bucket.
+struct ObjectEntry {
+    key: String,
+    size: u64,
+    last_modified: String,
+}
+
+fn extract_tag<'a>(body: &'a str, tag: &str) -> Option<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = body.find(&open)? + open.len();
+    let end = body[start..].find(&close)? + start;
+    Some(&body[start..end])
+}
+
+fn extract_all_tags<'a>(body: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut out = Vec::new();
+    let mut rest = body;
+    while let Some(start) = rest.find(&open) {
+        let after = &rest[start + open.len()..];
+        match after.find(&close) {
+            Some(end) => {
+                out.push(&after[..end]);
+                rest = &after[end + close.len()..];
+            }
+            None => break,
+        }
+    }
+    out
+}
+
+/// Parse an S3-compatible `ListBucketResult` (AWS/DO/Linode) into entries plus the
+/// continuation token to use for the next page, if any.
+fn parse_s3_listing(body: &str) -> (Vec<ObjectEntry>, Option<String>) {
+    let entries = extract_all_tags(body, "Contents")
+        .into_iter()
+        .map(|block| ObjectEntry {
+            key: extract_tag(block, "Key").unwrap_or_default().to_string(),
+            size: extract_tag(block, "Size").and_then(|s| s.parse().ok()).unwrap_or(0),
+            last_modified: extract_tag(block, "LastModified").unwrap_or_default().to_string(),
+        })
+        .collect();
+    let truncated = extract_tag(body, "IsTruncated") == Some("true");
+    let next_token = truncated
+        .then(|| extract_tag(body, "NextContinuationToken"))
+        .flatten()
+        .map(|s| s.to_string());
+    (entries, next_token)
+}
+
+/// Parse an Azure `EnumerationResults` document into entries plus the next marker, if any.
+fn parse_azure_listing(body: &str) -> (Vec<ObjectEntry>, Option<String>) {
+    let entries = extract_all_tags(body, "Blob")
+        .into_iter()
+        .map(|block| ObjectEntry {
+            key: extract_tag(block, "Name").unwrap_or_default().to_string(),
+            size: extract_tag(block, "Content-Length").and_then(|s| s.parse().ok()).unwrap_or(0),
+            last_modified: extract_tag(block, "Last-Modified").unwrap_or_default().to_string(),
+        })
+        .collect();
+    let next_marker = extract_tag(body, "NextMarker").filter(|s| !s.is_empty()).map(|s| s.to_string());
+    (entries, next_marker)
+}
+
+fn extract_json_string_field(body: &str, field: &str) -> Option<String> {
+    let needle = format!("\"{}\"", field);
+    let after_key = &body[body.find(&needle)? + needle.len()..];
+    let colon = after_key.find(':')? + 1;
+    let value_start = after_key[colon..].trim_start();
+    let quote = value_start.strip_prefix('"')?;
+    let end = quote.find('"')?;
+    Some(quote[..end].to_string())
+}
+
+/// Split the JSON array found under `key` into its top-level `{...}` object substrings,
+/// without pulling in a full JSON parser for what the rest of this tool treats as text.
+fn json_array_objects<'a>(body: &'a str, key: &str) -> Vec<&'a str> {
+    let needle = format!("\"{}\"", key);
+    let Some(key_pos) = body.find(&needle) else {
+        return Vec::new();
+    };
+    let mut objects = Vec::new();
+    let mut depth = 0i32;
+    let mut obj_start = None;
+    for (i, c) in body[key_pos..].char_indices() {
+        match c {
+            '{' => {
+                if depth == 0 {
+                    obj_start = Some(i);
+                }
+                depth += 1;
+            }
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    if let Some(start) = obj_start.take() {
+                        objects.push(&body[key_pos + start..key_pos + i + 1]);
+                    }
+                }
+            }
+            ']' if depth == 0 => break,
+            _ => {}
+        }
+    }
+    objects
+}
+
+/// Parse a GCS JSON API bucket listing (`storage/v1/b/{bucket}/o`) into entries plus
+/// the next page token, if any.
+fn parse_gcp_listing(body: &str) -> (Vec<ObjectEntry>, Option<String>) {
+    let entries = json_array_objects(body, "items")
+        .into_iter()
+        .map(|obj| ObjectEntry {
+            key: extract_json_string_field(obj, "name").unwrap_or_default(),
+            size: extract_json_string_field(obj, "size").and_then(|s| s.parse().ok()).unwrap_or(0),
+            last_modified: extract_json_string_field(obj, "updated").unwrap_or_default(),
+        })
+        .collect();
+    let next_page_token = extract_json_string_field(body, "nextPageToken");
+    (entries, next_page_token)
+}
+
+/// Percent-encode a query parameter value. Pagination tokens are base64 and routinely
+/// contain `+`, `/` and `=`; servers decode an unescaped `+` as a space, which corrupts
+/// the token and breaks pagination, so every reserved/non-alphanumeric byte is escaped.
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Enumerate up to `max_keys` objects in a readable bucket, following each
+/// provider's pagination scheme until the listing is exhausted or the cap is hit.
+async fn list_bucket(client: &Client, policy: &RequestPolicy, t: &BucketTarget, max_keys: usize) -> Vec<ObjectEntry> {
+    let mut entries = Vec::new();
+    let mut page_token: Option<String> = None;
+    loop {
+        let url = match t.provider {
+            Provider::AwsS3 | Provider::DigitalOceanSpaces | Provider::LinodeObjStorage => {
+                let mut url = format!("http://{}.{}/?list-type=2", t.bucket, get_provider_domain(&t.provider, t.region.as_deref()));
+                if let Some(token) = &page_token {
+                    url.push_str(&format!("&continuation-token={}", percent_encode(token)));
+                }
+                url
+            }
+            Provider::AzureBlob => {
+                let mut url = format!("https://{}.blob.core.windows.net/?restype=container&comp=list", t.bucket);
+                if let Some(marker) = &page_token {
+                    url.push_str(&format!("&marker={}", percent_encode(marker)));
+                }
+                url
+            }
+            Provider::GcpStorage => {
+                let mut url = format!("https://storage.googleapis.com/storage/v1/b/{}/o", t.bucket);
+                if let Some(token) = &page_token {
+                    url.push_str(&format!("?pageToken={}", percent_encode(token)));
+                }
+                url
+            }
+            Provider::Unknown => break,
+        };
+
+        let body = match send_with_policy(client.get(&url), policy).await {
+            Some(resp) if resp.status().is_success() => resp.text().await.unwrap_or_default(),
+            _ => break,
+        };
+
+        let (mut page, next_token) = match t.provider {
+            Provider::AwsS3 | Provider::DigitalOceanSpaces | Provider::LinodeObjStorage => parse_s3_listing(&body),
+            Provider::AzureBlob => parse_azure_listing(&body),
+            Provider::GcpStorage => parse_gcp_listing(&body),
+            Provider::Unknown => break,
+        };
+
+        entries.append(&mut page);
+        entries.truncate(max_keys);
+        if entries.len() >= max_keys || next_token.is_none() {
+            break;
+        }
+        page_token = next_token;
+    }
+    entries
+}
+
+/// A permission grant discovered on a bucket/object ACL or policy document.
+struct AclGrant {
+    grantee: String,
+    permission: String,
+}
+
+fn is_public_grantee(grantee: &str) -> bool {
+    matches!(grantee, "AllUsers" | "AuthenticatedUsers" | "allUsers" | "allAuthenticatedUsers")
+}
+
+/// Parse an S3-compatible `AccessControlPolicy` document into its grants.
+fn parse_s3_acl(body: &str) -> Vec<AclGrant> {
+    extract_all_tags(body, "Grant")
+        .into_iter()
+        .filter_map(|block| {
+            let grantee = extract_tag(block, "URI")
+                .map(|uri| uri.rsplit('/').next().unwrap_or(uri).to_string())
+                .or_else(|| extract_tag(block, "DisplayName").map(|s| s.to_string()))?;
+            let permission = extract_tag(block, "Permission")?.to_string();
+            Some(AclGrant { grantee, permission })
+        })
+        .collect()
+}
+
+/// Parse an S3 bucket policy JSON document for `Allow` statements with a wildcard
+/// principal, i.e. grants open to anyone rather than a specific AWS account.
+fn parse_s3_policy(body: &str) -> Vec<AclGrant> {
+    json_array_objects(body, "Statement")
+        .into_iter()
+        .filter(|stmt| extract_json_string_field(stmt, "Effect").as_deref() == Some("Allow"))
+        .filter(|stmt| {
+            // Policies are returned as stored, so whitespace around `:` varies; strip it
+            // before matching rather than requiring the exact compact form.
+            let compact: String = stmt.chars().filter(|c| !c.is_whitespace()).collect();
+            compact.contains("\"Principal\":\"*\"") || compact.contains("\"AWS\":\"*\"") || compact.contains("\"AWS\":[\"*\"]")
+        })
+        .map(|stmt| AclGrant {
+            grantee: "AllUsers".to_string(),
+            permission: extract_json_string_field(stmt, "Action").unwrap_or_else(|| "*".to_string()),
+        })
+        .collect()
+}
+
+/// Parse a GCS JSON API ACL document (`storage/v1/b/{bucket}/acl`) into its grants.
+fn parse_gcp_acl(body: &str) -> Vec<AclGrant> {
+    json_array_objects(body, "items")
+        .into_iter()
+        .filter_map(|obj| {
+            let grantee = extract_json_string_field(obj, "entity")?;
+            let permission = extract_json_string_field(obj, "role")?;
+            Some(AclGrant { grantee, permission })
+        })
+        .collect()
+}
+
+/// Azure containers don't expose per-grantee ACLs over anonymous HTTP; the closest
+/// analog is the container's public access level, reported via the
+/// `x-ms-blob-public-access` response header on the container ACL endpoint.
+async fn probe_azure_acl(client: &Client, policy: &RequestPolicy, t: &BucketTarget) -> Vec<AclGrant> {
+    let url = format!("https://{}.blob.core.windows.net/?restype=container&comp=acl", t.bucket);
+    let Some(resp) = send_with_policy(client.get(&url), policy).await else {
+        return Vec::new();
+    };
+    match resp.headers().get("x-ms-blob-public-access").and_then(|v| v.to_str().ok()) {
+        Some("container") => vec![AclGrant { grantee: "AllUsers".to_string(), permission: "READ+LIST".to_string() }],
+        Some("blob") => vec![AclGrant { grantee: "AllUsers".to_string(), permission: "READ".to_string() }],
+        _ => Vec::new(),
     }
 }
 
-fn main() {
-    print_banner();
+/// Probe a bucket's ACL and (for S3-compatible providers) policy document, returning
+/// only the grants that are open to the public rather than a specific principal.
+async fn probe_acl(client: &Client, policy: &RequestPolicy, t: &BucketTarget) -> Vec<AclGrant> {
+    let mut grants = match t.provider {
+        Provider::AwsS3 | Provider::DigitalOceanSpaces | Provider::LinodeObjStorage => {
+            let domain = get_provider_domain(&t.provider, t.region.as_deref());
+            let acl_url = format!("http://{}.{}/?acl", t.bucket, domain);
+            let policy_url = format!("http://{}.{}/?policy", t.bucket, domain);
+            let acl_body = match send_with_policy(client.get(&acl_url), policy).await {
+                Some(resp) if resp.status().is_success() => resp.text().await.unwrap_or_default(),
+                _ => String::new(),
+            };
+            let policy_body = match send_with_policy(client.get(&policy_url), policy).await {
+                Some(resp) if resp.status().is_success() => resp.text().await.unwrap_or_default(),
+                _ => String::new(),
+            };
+            let mut grants = parse_s3_acl(&acl_body);
+            grants.extend(parse_s3_policy(&policy_body));
+            grants
+        }
+        Provider::AzureBlob => probe_azure_acl(client, policy, t).await,
+        Provider::GcpStorage => {
+            let url = format!("https://storage.googleapis.com/storage/v1/b/{}/acl", t.bucket);
+            match send_with_policy(client.get(&url), policy).await {
+                Some(resp) if resp.status().is_success() => parse_gcp_acl(&resp.text().await.unwrap_or_default()),
+                _ => Vec::new(),
+            }
+        }
+        Provider::Unknown => Vec::new(),
+    };
+    grants.retain(|g| is_public_grantee(&g.grantee));
+    grants
+}
+
+fn access_label(level: AccessLevel, verb: &str) -> Option<String> {
+    match level {
+        AccessLevel::Public => Some(format!("[{}]", verb)),
+        AccessLevel::AuthenticatedOnly => Some(format!("[{} (auth)]", verb)),
+        AccessLevel::Private => None,
+    }
+}
+
+fn delete_label(deleted: Option<bool>) -> Option<&'static str> {
+    match deleted {
+        Some(true) => Some("[delete]"),
+        Some(false) => Some("[delete failed]"),
+        None => None,
+    }
+}
+
+/// Machine-readable rendering of one target's probe results, for `--output`.
+#[derive(Serialize)]
+struct BucketResult {
+    provider: String,
+    bucket: String,
+    url: String,
+    read: bool,
+    read_auth_only: bool,
+    write: bool,
+    write_auth_only: bool,
+    delete: Option<bool>,
+    /// Public ACL/policy grants as "GRANTEE:PERMISSION", joined with ";"; empty unless --acl
+    /// was passed. Kept as a flat string (not a `Vec`) so every output format, including CSV,
+    /// can serialize a `BucketResult` as a single flat record.
+    acl_grants: String,
+}
+
+impl BucketResult {
+    fn new(t: &BucketTarget, read: AccessLevel, write: AccessLevel, delete: Option<bool>, acl_grants: Vec<AclGrant>) -> Self {
+        BucketResult {
+            provider: provider_str(&t.provider).to_string(),
+            bucket: t.bucket.clone(),
+            url: construct_read_url(t),
+            read: read == AccessLevel::Public,
+            read_auth_only: read == AccessLevel::AuthenticatedOnly,
+            write: write == AccessLevel::Public,
+            write_auth_only: write == AccessLevel::AuthenticatedOnly,
+            delete,
+            acl_grants: acl_grants
+                .into_iter()
+                .map(|g| format!("{}:{}", g.grantee, g.permission))
+                .collect::<Vec<_>>()
+                .join(";"),
+        }
+    }
+}
+
+fn write_results(results: &[BucketResult], format: OutputFormat) {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    match format {
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(results).expect("serializing results to JSON");
+            let _ = writeln!(out, "{}", json);
+        }
+        OutputFormat::Jsonl => {
+            for result in results {
+                let json = serde_json::to_string(result).expect("serializing a result to JSON");
+                let _ = writeln!(out, "{}", json);
+            }
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            for result in results {
+                writer.serialize(result).expect("serializing a result to CSV");
+            }
+            writer.flush().expect("flushing CSV output");
+        }
+    }
+}
+
+fn colorize(use_color: bool, ansi_code: &str, text: &str) -> String {
+    if use_color {
+        format!("\x1b[{}m{}\x1b[0m", ansi_code, text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// Resolve, probe, and (optionally) list/ACL-check a single target end to end.
+/// Runs as one `buffer_unordered` task among up to `--concurrency` others.
+#[allow(clippy::too_many_arguments)]
+async fn scan_target(
+    client: Client,
+    policy: Arc<RequestPolicy>,
+    creds: Option<Arc<Credentials>>,
+    region_override: Option<String>,
+    line: String,
+    human: bool,
+    use_color: bool,
+    list: bool,
+    max_keys: usize,
+    acl: bool,
+    cleanup: bool,
+) -> Option<BucketResult> {
+    let mut target = extract_target(&line)?;
+    resolve_region(&client, &policy, &mut target, region_override.as_deref()).await;
+
+    let readable = check_read(&client, &policy, &target, creds.as_deref()).await;
+    let (writable, deleted) = check_write(&client, &policy, &target, creds.as_deref(), cleanup).await;
+
+    if human {
+        let labels: Vec<String> = [access_label(readable, "read"), access_label(writable, "write")]
+            .into_iter()
+            .flatten()
+            .chain(delete_label(deleted).map(|s| s.to_string()))
+            .collect();
+        let status = if labels.is_empty() {
+            colorize(use_color, "31", "[no access]")
+        } else if readable == AccessLevel::Public || writable == AccessLevel::Public {
+            colorize(use_color, "32", &labels.join(" "))
+        } else {
+            colorize(use_color, "33", &labels.join(" "))
+        };
+        println!("{} | {} | {}", provider_str(&target.provider), target.bucket, status);
+        if list && readable == AccessLevel::Public {
+            for entry in list_bucket(&client, &policy, &target, max_keys).await {
+                println!("    {} | {} bytes | {}", entry.key, entry.size, entry.last_modified);
+            }
+        }
+    }
+
+    let acl_grants = if acl { probe_acl(&client, &policy, &target).await } else { Vec::new() };
+    if human {
+        for grant in &acl_grants {
+            println!("    [acl] {}: {}", grant.grantee, grant.permission);
+        }
+    }
+
+    Some(BucketResult::new(&target, readable, writable, deleted, acl_grants))
+}
+
+#[tokio::main]
+async fn main() {
     let args = Args::parse();
+    let human = args.output.is_none();
+    if human {
+        print_banner();
+    }
+    let use_color = human && io::stdout().is_terminal();
+    let creds = Credentials::from_env_or_args(&args).map(Arc::new);
+    let policy = Arc::new(RequestPolicy::new(&args));
 
     // Read lines from file if specified, otherwise from stdin
-    let lines: Vec<String> = if let Some(input_file) = args.input {
-        let file = File::open(&input_file)
+    let lines: Vec<String> = if let Some(input_file) = &args.input {
+        let file = File::open(input_file)
             .unwrap_or_else(|e| panic!("Error opening file {}: {}", input_file, e));
         BufReader::new(file)
             .lines()
-            .filter_map(|line| line.ok())
+            .map_while(Result::ok)
             .filter(|line| !line.trim().is_empty())
             .collect()
     } else {
@@ -171,42 +1043,71 @@ fn main() {
         stdin
             .lock()
             .lines()
-            .filter_map(|line| line.ok())
+            .map_while(Result::ok)
             .filter(|line| !line.trim().is_empty())
             .collect()
     };
 
     let client = Client::new();
-    let results: Vec<_> = lines.par_iter().map(|line| {
-        if let Some(target) = extract_target(line) {
-            let readable = check_read(&client, &target);
-            let writable = check_write(&client, &target);
-            let status = match (readable, writable) {
-                (true, true) => "\x1b[32m[read] [write]\x1b[0m",
-                (true, false) => "\x1b[32m[read]\x1b[0m",
-                (false, true) => "\x1b[33m[write]\x1b[0m",
-                (false, false) => "\x1b[31m[no access]\x1b[0m",
-            };
-            println!("{} | {} | {}", provider_str(&target.provider), target.bucket, status);
-            Some((target.provider, target.bucket, readable, writable))
-        } else {
-            None
-        }
-    }).filter_map(|r| r).collect();
+    let region_override = args.region.clone();
+    let results: Vec<BucketResult> = stream::iter(lines.into_iter())
+        .map(|line| {
+            let client = client.clone();
+            let policy = Arc::clone(&policy);
+            let creds = creds.clone();
+            let region_override = region_override.clone();
+            async move {
+                scan_target(
+                    client,
+                    policy,
+                    creds,
+                    region_override,
+                    line,
+                    human,
+                    use_color,
+                    args.list,
+                    args.max_keys,
+                    args.acl,
+                    !args.no_cleanup,
+                )
+                .await
+            }
+        })
+        .buffer_unordered(args.concurrency.max(1))
+        .filter_map(|result| async move { result })
+        .collect()
+        .await;
+
+    if let Some(format) = args.output {
+        write_results(&results, format);
+        return;
+    }
 
     // Print summary
-    println!("\n\x1b[1;34mBuckets with open permissions:\x1b[0m");
-    for (provider, bucket, readable, writable) in &results {
-        if *readable || *writable {
-            let mut perms = vec![];
-            if *readable { perms.push("read"); }
-            if *writable { perms.push("write"); }
-            println!(
-                "{} | {} | [{}]",
-                provider_str(provider),
-                bucket,
-                perms.join(", ")
-            );
+    println!("\n{}", colorize(use_color, "1;34", "Buckets with open permissions:"));
+    for result in &results {
+        if result.read || result.read_auth_only || result.write || result.write_auth_only || !result.acl_grants.is_empty() {
+            let read_label = if result.read {
+                Some("[read]".to_string())
+            } else if result.read_auth_only {
+                Some("[read (auth)]".to_string())
+            } else {
+                None
+            };
+            let write_label = if result.write {
+                Some("[write]".to_string())
+            } else if result.write_auth_only {
+                Some("[write (auth)]".to_string())
+            } else {
+                None
+            };
+            let perms: Vec<String> = [read_label, write_label]
+                .into_iter()
+                .flatten()
+                .chain(delete_label(result.delete).map(|s| s.to_string()))
+                .chain(result.acl_grants.split(';').filter(|g| !g.is_empty()).map(|g| format!("[acl: {}]", g)))
+                .collect();
+            println!("{} | {} | {}", result.provider, result.bucket, perms.join(", "));
         }
     }
 }